@@ -0,0 +1,40 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rustomp::frame::Header;
+
+fn make_header() -> Header {
+    let mut header = Header::new();
+    header.add_field("Content-Type", "application/json");
+    header.set_content_length(128);
+    header.add_field("Destination", "/queue/orders");
+    header.add_field("Receipt", "message-1234");
+    header
+}
+
+fn bench_write_to(c: &mut Criterion) {
+    let header = make_header();
+    let mut buf: Vec<u8> = Vec::new();
+
+    c.bench_function("Header::write_to", |b| {
+        b.iter(|| {
+            buf.clear();
+            header.write_to(black_box(&mut buf)).unwrap();
+            black_box(&buf);
+        })
+    });
+}
+
+fn bench_encode_into(c: &mut Criterion) {
+    let header = make_header();
+    let mut buf: Vec<u8> = Vec::new();
+
+    c.bench_function("Header::encode_into", |b| {
+        b.iter(|| {
+            buf.clear();
+            header.encode_into(black_box(&mut buf));
+            black_box(&buf);
+        })
+    });
+}
+
+criterion_group!(benches, bench_write_to, bench_encode_into);
+criterion_main!(benches);
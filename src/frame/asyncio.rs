@@ -0,0 +1,302 @@
+//! Async frame reading/writing over `tokio`'s `AsyncRead`/`AsyncWrite`.
+//!
+//! This mirrors [`Frame::read_from`](super::Frame::read_from) and
+//! [`Frame::write_to`](super::Frame::write_to), but drives an
+//! `AsyncBufRead`/`AsyncWrite` pair the way a language server drives
+//! `BufReader<Stdin>` with `AsyncBufReadExt`, so a broker or gateway can
+//! service many connections without a blocking thread per connection.
+//!
+//! One difference from the sync reader: a header section that ends (by end
+//! of stream or by reaching `MAX_HEADER_SIZE`) before its blank line is a
+//! `ReadError::Format` here, where the sync reader ends the headers there.
+
+use super::error::ReadError;
+use super::string;
+use super::{Command, Header, EOL, MAX_COMMAND_SIZE, MAX_HEADER_SIZE, NULL};
+use std::io;
+use std::pin::Pin;
+use std::str;
+use std::str::FromStr;
+use std::task::{Context, Poll};
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, ReadBuf,
+};
+
+/// The async counterpart of [`Body`](super::Body): a frame body that stops
+/// at `Content-Length` bytes, or at the NULL terminator when no
+/// `Content-Length` header was present.
+pub struct AsyncBody<'a> {
+    inner: &'a mut (dyn AsyncBufRead + Unpin + Send),
+    limit: u64,
+    done: bool,
+}
+
+impl<'a> AsyncBody<'a> {
+    fn new<R: AsyncBufRead + Unpin + Send>(reader: &'a mut R) -> Self {
+        AsyncBody {
+            inner: reader,
+            limit: 0,
+            done: false,
+        }
+    }
+
+    fn with_length<R: AsyncBufRead + Unpin + Send>(reader: &'a mut R, content_length: u64) -> Self {
+        AsyncBody {
+            inner: reader,
+            limit: content_length,
+            done: false,
+        }
+    }
+}
+
+impl<'a> AsyncRead for AsyncBody<'a> {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+
+        if this.done {
+            return Poll::Ready(Ok(()));
+        }
+
+        if this.limit > 0 {
+            let max = std::cmp::min(buf.remaining() as u64, this.limit) as usize;
+            let before = buf.filled().len();
+            let unfilled = buf.initialize_unfilled_to(max);
+            let mut sub = ReadBuf::new(unfilled);
+
+            return match Pin::new(&mut *this.inner).poll_read(cx, &mut sub) {
+                Poll::Ready(Ok(())) => {
+                    let n = sub.filled().len();
+                    buf.set_filled(before + n);
+                    this.limit -= n as u64;
+                    Poll::Ready(Ok(()))
+                }
+                other => other,
+            };
+        }
+
+        match Pin::new(&mut *this.inner).poll_fill_buf(cx) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+            Poll::Ready(Ok(available)) => {
+                if available.is_empty() {
+                    this.done = true;
+                    return Poll::Ready(Ok(()));
+                }
+
+                let (found, used) = match memchr::memchr(NULL, available) {
+                    Some(i) => (true, i),
+                    None => (false, available.len()),
+                };
+                let n = std::cmp::min(used, buf.remaining());
+                buf.put_slice(&available[..n]);
+
+                let consumed = if found && n == used { n + 1 } else { n };
+                Pin::new(&mut *this.inner).consume(consumed);
+
+                if found && n == used {
+                    this.done = true;
+                }
+                Poll::Ready(Ok(()))
+            }
+        }
+    }
+}
+
+/// The async counterpart of [`Frame`](super::Frame).
+pub struct AsyncFrame<'a> {
+    pub command: Command,
+    pub header: Header,
+    pub body: AsyncBody<'a>,
+}
+
+impl<'a> AsyncFrame<'a> {
+    fn with_header(command: Command, header: Header, body: AsyncBody<'a>) -> Self {
+        AsyncFrame {
+            command,
+            header,
+            body,
+        }
+    }
+
+    /// Reads a command line, headers, and sets up a streaming body from an
+    /// `AsyncBufRead`, like [`Frame::read_from`](super::Frame::read_from)
+    /// does for a sync `BufRead` (see the module docs for how an unterminated
+    /// header section is handled differently).
+    pub async fn read_from<R: AsyncBufRead + Unpin + Send>(
+        reader: &'a mut R,
+    ) -> Result<Self, ReadError> {
+        let command = read_command(&mut *reader).await?;
+        let header = read_header(&mut *reader).await?;
+
+        let clen = header.get_first("Content-Length");
+
+        let body = match clen {
+            Some(n) => {
+                let content_length = n
+                    .parse::<u64>()
+                    .map_err(|e| ReadError::Format(e.to_string()))?;
+                AsyncBody::with_length(reader, content_length)
+            }
+            None => AsyncBody::new(reader),
+        };
+
+        Ok(AsyncFrame::with_header(command, header, body))
+    }
+
+    /// Writes the command, headers, body, and NULL terminator to an
+    /// `AsyncWrite`, exactly like [`Frame::write_to`](super::Frame::write_to)
+    /// does for a sync `Write`.
+    pub async fn write_to<W: AsyncWrite + Unpin>(&mut self, mut w: W) -> io::Result<u64> {
+        let mut bytes_written: u64 = 0;
+        bytes_written += w.write(self.command.to_string().as_bytes()).await? as u64;
+        bytes_written += w.write(&[EOL]).await? as u64;
+
+        let mut header_buf = Vec::new();
+        self.header.write_to(&mut header_buf)?;
+        w.write_all(&header_buf).await?;
+        bytes_written += header_buf.len() as u64;
+
+        bytes_written += w.write(&[EOL]).await? as u64;
+        bytes_written += tokio::io::copy(&mut self.body, &mut w).await?;
+        bytes_written += w.write(&[NULL]).await? as u64;
+
+        w.flush().await.and(Ok(bytes_written))
+    }
+}
+
+async fn read_command<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Command, ReadError> {
+    let mut limited = reader.take(MAX_COMMAND_SIZE);
+    let mut buffer: Vec<u8> = Vec::new();
+    let bytes_read = limited.read_until(EOL, &mut buffer).await?;
+
+    if bytes_read < 1 {
+        return Err(ReadError::Format(String::from("empty command")));
+    }
+    if buffer.last() == Some(&EOL) {
+        buffer.pop();
+    }
+    let raw_string_command = str::from_utf8(&buffer)?;
+    let clean_string_command = raw_string_command.trim();
+
+    if clean_string_command.is_empty() {
+        return Err(ReadError::Format(String::from("empty command")));
+    }
+    Command::from_str(clean_string_command).map_err(ReadError::Format)
+}
+
+async fn read_header<R: AsyncBufRead + Unpin>(reader: &mut R) -> Result<Header, ReadError> {
+    let mut header = Header::new();
+    // Bound each `read_until` the same way `read_command` bounds itself with
+    // `.take(MAX_COMMAND_SIZE)`, so a line with no terminator can't grow
+    // `buffer` without limit before the loop ever gets a chance to check it.
+    let mut limited = reader.take(MAX_HEADER_SIZE);
+
+    loop {
+        let mut buffer: Vec<u8> = Vec::new();
+        limited.read_until(EOL, &mut buffer).await?;
+
+        if buffer.last() != Some(&EOL) {
+            return Err(if limited.limit() == 0 {
+                ReadError::Format(String::from("header section too large"))
+            } else {
+                ReadError::Format(String::from(
+                    "unexpected end of stream while reading headers",
+                ))
+            });
+        }
+        buffer.pop();
+        if buffer.is_empty() {
+            break;
+        }
+
+        let line = str::from_utf8(&buffer)?;
+        let clean_line = line.trim_end_matches('\r');
+        let parts: Vec<&str> = clean_line.splitn(2, ':').collect();
+
+        if parts.len() < 2 {
+            return Err(ReadError::Format(format!(
+                "invalid number of header field parts. Expected 2, got {}",
+                parts.len()
+            )));
+        }
+        let field_name = string::decode(parts[0])?;
+        let field_value = string::decode(parts[1])?;
+
+        let clean_field_name = field_name.trim();
+        let clean_field_value = field_value.trim_start();
+
+        if clean_field_name.is_empty() {
+            return Err(ReadError::Format(String::from("empty header field name")));
+        }
+        header.add_field(clean_field_name, clean_field_value);
+    }
+    Ok(header)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tokio::io::BufReader;
+
+    #[tokio::test]
+    async fn async_frame_reads_body_with_content_length() {
+        let input =
+            b"CONNECT\nContent-Length: 17\nContent-Type: application/json\n\n{\"name\":\"Joshua\"}\0"
+                .to_vec();
+        let mut reader = BufReader::new(io::Cursor::new(input));
+        let mut frame = AsyncFrame::read_from(&mut reader).await.unwrap();
+
+        let mut body = Vec::new();
+        frame.body.read_to_end(&mut body).await.unwrap();
+
+        assert_eq!(Command::Connect, frame.command);
+        assert_eq!(Some("17"), frame.header.get_first("Content-Length"));
+        assert_eq!(b"{\"name\":\"Joshua\"}".to_vec(), body);
+    }
+
+    #[tokio::test]
+    async fn async_frame_round_trips_through_write_to() {
+        let input =
+            b"CONNECT\nContent-Length: 17\nContent-Type: application/json\n\n{\"name\":\"Joshua\"}\0"
+                .to_vec();
+        let mut reader = BufReader::new(io::Cursor::new(input.clone()));
+        let mut frame = AsyncFrame::read_from(&mut reader).await.unwrap();
+
+        let mut buffer: Vec<u8> = Vec::new();
+        frame.write_to(&mut buffer).await.unwrap();
+
+        assert_eq!(input, buffer);
+    }
+
+    #[tokio::test]
+    async fn read_header_rejects_an_unterminated_line_at_the_size_limit() {
+        let mut input = Vec::from(&b"CONNECT\n"[..]);
+        input.extend(std::iter::repeat(b'x').take((MAX_HEADER_SIZE + 1) as usize));
+        let mut reader = BufReader::new(io::Cursor::new(input));
+
+        let err = AsyncFrame::read_from(&mut reader).await.unwrap_err();
+        assert!(matches!(err, ReadError::Format(_)));
+    }
+
+    #[tokio::test]
+    async fn async_body_read_with_a_small_buffer_does_not_drop_bytes_before_the_null_terminator() {
+        let mut reader = BufReader::new(io::Cursor::new(b"hello world\0after".to_vec()));
+        let mut body = AsyncBody::new(&mut reader);
+
+        let mut out: Vec<u8> = Vec::new();
+        let mut chunk = [0u8; 3];
+        loop {
+            let n = body.read(&mut chunk).await.unwrap();
+            if n == 0 {
+                break;
+            }
+            out.extend_from_slice(&chunk[..n]);
+        }
+
+        assert_eq!(b"hello world".to_vec(), out);
+    }
+}
@@ -0,0 +1,46 @@
+use super::io;
+use core::fmt;
+use core::str::Utf8Error;
+
+#[cfg(feature = "std")]
+use std::string::String;
+
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+
+/// Failure modes encountered while reading a [`Frame`](super::Frame) off the
+/// wire.
+#[derive(Debug)]
+pub enum ReadError {
+    /// The underlying reader returned an I/O error.
+    Io(io::Error),
+    /// A command or header line was not valid UTF-8.
+    Utf8(Utf8Error),
+    /// The bytes read did not match the STOMP frame grammar.
+    Format(String),
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReadError::Io(e) => write!(f, "io error: {}", e),
+            ReadError::Utf8(e) => write!(f, "invalid utf-8: {}", e),
+            ReadError::Format(msg) => write!(f, "malformed frame: {}", msg),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ReadError {}
+
+impl From<io::Error> for ReadError {
+    fn from(e: io::Error) -> Self {
+        ReadError::Io(e)
+    }
+}
+
+impl From<Utf8Error> for ReadError {
+    fn from(e: Utf8Error) -> Self {
+        ReadError::Utf8(e)
+    }
+}
@@ -0,0 +1,93 @@
+//! Internal I/O facade.
+//!
+//! Everything in this module resolves to `std::io` when the `std` feature is
+//! enabled (the default) or to `core_io` when it isn't, so the frame parser
+//! can run in `#![no_std]` environments such as a microcontroller talking
+//! STOMP over a serial or TCP link.
+//!
+//! This facade, and the `tokio`/`serde` gates alongside it in
+//! [`super`](super), assume a manifest (outside this source tree) that
+//! declares exactly three optional features named `std` (default-on),
+//! `tokio`, and `serde` — with `core_io` as `std`'s sole alternative
+//! dependency, and `serde`'s own `cfg` in `mod.rs` additionally requiring
+//! `std` so JSON bridging never gets offered in a `no_std` build. Every
+//! `cfg(feature = ...)` in this crate uses those three names consistently;
+//! confirm the manifest matches before relying on any of them.
+
+#[cfg(feature = "std")]
+pub(crate) use std::io::{
+    copy, empty, sink, BufRead, BufWriter, Error, ErrorKind, Read, Result, Write,
+};
+
+#[cfg(not(feature = "std"))]
+pub(crate) use core_io::{copy, empty, sink, BufRead, BufWriter, Error, Read, Result, Write};
+
+/// Reads bytes from `inner` up to, and consuming, the first occurrence of
+/// `delim`. The delimiter itself is not yielded.
+pub(crate) struct DelimitedReader<R> {
+    inner: R,
+    delim: u8,
+    done: bool,
+}
+
+impl<R: Read> DelimitedReader<R> {
+    pub(crate) fn new(inner: R, delim: u8) -> Self {
+        DelimitedReader {
+            inner,
+            delim,
+            done: false,
+        }
+    }
+}
+
+impl<R: Read> Read for DelimitedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if self.done || buf.is_empty() {
+            return Ok(0);
+        }
+
+        let mut n = 0;
+        let mut byte = [0u8];
+
+        while n < buf.len() {
+            if self.inner.read(&mut byte)? == 0 {
+                self.done = true;
+                break;
+            }
+            if byte[0] == self.delim {
+                self.done = true;
+                break;
+            }
+            buf[n] = byte[0];
+            n += 1;
+        }
+        Ok(n)
+    }
+}
+
+/// Reads at most `limit` bytes from `inner`, then behaves as EOF.
+pub(crate) struct LimitedReader<R> {
+    inner: R,
+    remaining: u64,
+}
+
+impl<R: Read> LimitedReader<R> {
+    pub(crate) fn new(inner: R, limit: u64) -> Self {
+        LimitedReader {
+            inner,
+            remaining: limit,
+        }
+    }
+}
+
+impl<R: Read> Read for LimitedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        if self.remaining == 0 {
+            return Ok(0);
+        }
+        let max = core::cmp::min(buf.len() as u64, self.remaining) as usize;
+        let read = self.inner.read(&mut buf[..max])?;
+        self.remaining -= read as u64;
+        Ok(read)
+    }
+}
@@ -1,23 +1,56 @@
+#[cfg(not(feature = "std"))]
+extern crate alloc;
+
+#[cfg(feature = "tokio")]
+mod asyncio;
 mod error;
 mod io;
+// JSON bridging/logging is inherently a `std` use case, so `serde` support
+// requires `std` too rather than being routed through the `no_std` facade.
+#[cfg(all(feature = "serde", feature = "std"))]
+mod serde_support;
 mod string;
 
+#[cfg(feature = "tokio")]
+pub use asyncio::{AsyncBody, AsyncFrame};
+#[cfg(all(feature = "serde", feature = "std"))]
+pub use serde_support::FrameView;
+
 use error::ReadError;
 use io::DelimitedReader;
+
+#[cfg(feature = "std")]
 use std::collections::BTreeMap;
-use std::fmt;
-use std::io as stdio;
-use std::io::BufWriter;
-use std::io::{BufRead, Read, Write};
-use std::str;
-use std::str::FromStr;
+#[cfg(feature = "std")]
+use std::string::String;
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+#[cfg(not(feature = "std"))]
+use alloc::collections::BTreeMap;
+#[cfg(not(feature = "std"))]
+use alloc::format;
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+#[cfg(not(feature = "std"))]
+use alloc::vec;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+use core::fmt;
+use core::str;
+use core::str::FromStr;
+use io::{BufRead, BufWriter, Read, Write};
+
+#[cfg(feature = "std")]
+use std::io::IoSlice;
 
 const MAX_COMMAND_SIZE: u64 = 1024;
 const MAX_HEADER_SIZE: u64 = 1024 * 1000;
 const NULL: u8 = b'\0';
 const EOL: u8 = b'\n';
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Command {
     Connect,
     Stomp,
@@ -36,11 +69,12 @@ pub enum Command {
     Error,
 }
 
-impl fmt::Display for Command {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+impl Command {
+    /// The wire representation of this command, without allocating.
+    pub fn as_str(&self) -> &'static str {
         use self::Command::*;
 
-        let value = match self {
+        match self {
             Connect => "CONNECT",
             Stomp => "STOMP",
             Connected => "CONNECTED",
@@ -56,9 +90,13 @@ impl fmt::Display for Command {
             Message => "MESSAGE",
             Receipt => "RECEIPT",
             Error => "ERROR",
-        };
+        }
+    }
+}
 
-        write!(f, "{}", value)
+impl fmt::Display for Command {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.as_str())
     }
 }
 
@@ -89,7 +127,7 @@ impl FromStr for Command {
     }
 }
 
-#[derive(Default, PartialEq, Debug)]
+#[derive(Default, Clone, PartialEq, Debug)]
 pub struct Header {
     fields: BTreeMap<String, Vec<String>>,
 }
@@ -105,6 +143,17 @@ impl Header {
         self.fields.get(key)
     }
 
+    /// The semantic value of a (possibly repeated) header: per the STOMP 1.2
+    /// spec, when a header name appears more than once in a frame, only the
+    /// *first* occurrence has meaning. All raw values are still kept in
+    /// `fields` for round-tripping via [`write_to`](Header::write_to).
+    pub fn get_first(&self, key: &str) -> Option<&str> {
+        self.fields
+            .get(key)
+            .and_then(|values| values.first())
+            .map(String::as_str)
+    }
+
     pub fn add_field<T: Into<String>>(&mut self, key: T, value: T) {
         self.fields
             .entry(key.into())
@@ -126,17 +175,42 @@ impl Header {
         self.fields.remove(key);
     }
 
-    pub fn write_to<W: Write>(&self, mut w: W) -> stdio::Result<u64> {
+    /// Sets `Content-Length` to `len`, formatting it with `itoa` instead of
+    /// going through `Display`.
+    pub fn set_content_length(&mut self, len: u64) {
+        let mut itoa_buf = itoa::Buffer::new();
+        self.set_field("Content-Length", vec![String::from(itoa_buf.format(len))]);
+    }
+
+    pub fn write_to<W: Write>(&self, mut w: W) -> io::Result<u64> {
         let mut bytes_written: u64 = 0;
 
         for (k, v) in self.fields.iter() {
-            let field_str = format!("{}: {}\n", string::encode(k), string::encode(&v.join(",")));
-            let size = w.write(field_str.as_bytes())?;
-            bytes_written += size as u64;
+            for value in v.iter() {
+                let field_str = format!("{}: {}\n", string::encode(k), string::encode(value));
+                let size = w.write(field_str.as_bytes())?;
+                bytes_written += size as u64;
+            }
         }
         Ok(bytes_written)
     }
 
+    /// Appends every field as one `key: value\n` line per raw value — a
+    /// repeated header stays repeated on the wire, as STOMP 1.2 requires —
+    /// straight into `buf`, with no intermediate `String` allocation per
+    /// field. The caller is expected to reuse `buf` (see [`BufferPool`])
+    /// across many frames.
+    pub fn encode_into(&self, buf: &mut Vec<u8>) {
+        for (k, v) in self.fields.iter() {
+            for value in v.iter() {
+                string::encode_into(k, buf);
+                buf.extend_from_slice(b": ");
+                string::encode_into(value, buf);
+                buf.push(EOL);
+            }
+        }
+    }
+
     fn read_from<R: Read>(reader: R) -> Result<Self, ReadError> {
         let mut limited_reader = io::LimitedReader::new(reader, MAX_HEADER_SIZE);
         let mut header = Self::new();
@@ -151,7 +225,10 @@ impl Header {
             }
             let line = str::from_utf8(&buffer)?;
             let clean_line = line.trim_end_matches('\r');
-            let parts: Vec<&str> = clean_line.split(':').collect();
+            // Split on only the first colon: any further raw colons stay in
+            // the value. Escaped colons (`\c`) hold no `:` byte, so they
+            // never reach the split and are decoded below.
+            let parts: Vec<&str> = clean_line.splitn(2, ':').collect();
 
             if parts.len() < 2 {
                 return Err(ReadError::Format(format!(
@@ -159,8 +236,8 @@ impl Header {
                     parts.len()
                 )));
             }
-            let field_name = string::decode(parts[0]);
-            let field_value = string::decode(parts[1]);
+            let field_name = string::decode(parts[0])?;
+            let field_value = string::decode(parts[1])?;
 
             let clean_field_name = field_name.trim();
             let clean_field_value = field_value.trim_start();
@@ -197,39 +274,93 @@ impl<'a> Body<'a> {
         }
     }
 
-    pub fn close(&mut self) -> stdio::Result<()> {
-        stdio::copy(&mut self.inner, &mut stdio::sink()).map(|_| ())
+    pub fn close(&mut self) -> io::Result<()> {
+        io::copy(&mut self.inner, &mut io::sink()).map(|_| ())
     }
 }
 
 impl<'a> Read for Body<'a> {
-    fn read(&mut self, buf: &mut [u8]) -> stdio::Result<usize> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         if self.done {
             return Ok(0);
         }
 
         if self.limit > 0 {
-            let max = std::cmp::min(buf.len() as u64, self.limit) as usize;
+            let max = core::cmp::min(buf.len() as u64, self.limit) as usize;
             let read = self.inner.read(&mut buf[..max])?;
             self.limit -= read as u64;
             return Ok(read);
         }
-        let mut available = self.inner.fill_buf()?;
+
+        let available = self.inner.fill_buf()?;
+
+        if available.is_empty() {
+            self.done = true;
+            return Ok(0);
+        }
 
         let (found, used) = match memchr::memchr(NULL, available) {
-            Some(i) => {
-                self.done = true;
-                (true, (&available[..i]).read(buf)? + 1)
-            }
-            None => (false, available.read(buf)?),
+            Some(i) => (true, i),
+            None => (false, available.len()),
         };
-        self.inner.consume(used);
+        // Only consume (and declare ourselves done) once `buf` was large
+        // enough to actually copy out everything up to the NULL; otherwise
+        // a short caller-provided `buf` would make us drop the undelivered
+        // tail of the body and stop one read short of the real terminator.
+        let n = core::cmp::min(used, buf.len());
+        buf[..n].copy_from_slice(&available[..n]);
+
+        let consumed = if found && n == used { n + 1 } else { n };
+        self.inner.consume(consumed);
+
+        if found && n == used {
+            self.done = true;
+        }
+        Ok(n)
+    }
+}
 
-        if found {
-            return Ok(used - 1);
+/// Writes every byte of `bufs` to `w`, issuing vectored `write_vectored`
+/// calls and re-slicing whatever `w` didn't take in one go. Stable-Rust
+/// equivalent of the nightly-only `Write::write_all_vectored`.
+#[cfg(feature = "std")]
+fn write_all_vectored<W: Write>(w: &mut W, bufs: &[&[u8]]) -> io::Result<()> {
+    let mut idx = 0;
+    let mut offset = 0;
+
+    while idx < bufs.len() {
+        let slices: Vec<IoSlice<'_>> = bufs[idx..]
+            .iter()
+            .enumerate()
+            .map(|(i, buf)| {
+                if i == 0 {
+                    IoSlice::new(&buf[offset..])
+                } else {
+                    IoSlice::new(buf)
+                }
+            })
+            .collect();
+
+        let mut written = w.write_vectored(&slices)?;
+        if written == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::WriteZero,
+                "failed to write whole buffer",
+            ));
+        }
+
+        while idx < bufs.len() {
+            let remaining = bufs[idx].len() - offset;
+            if written < remaining {
+                offset += written;
+                break;
+            }
+            written -= remaining;
+            idx += 1;
+            offset = 0;
         }
-        return Ok(used);
     }
+    Ok(())
 }
 
 pub struct Frame<'a> {
@@ -255,19 +386,65 @@ impl<'a> Frame<'a> {
         }
     }
 
-    pub fn write_to<W: Write>(&mut self, w: W) -> stdio::Result<u64> {
+    pub fn write_to<W: Write>(&mut self, w: W) -> io::Result<u64> {
         let mut bw = BufWriter::new(w);
         let mut bytes_written: u64 = 0;
         bytes_written += bw.write(self.command.to_string().as_bytes())? as u64;
         bytes_written += bw.write(&[EOL])? as u64;
         bytes_written += self.header.write_to(&mut bw)?;
         bytes_written += bw.write(&[EOL])? as u64;
-        bytes_written += stdio::copy(&mut self.body, &mut bw)?;
+        bytes_written += io::copy(&mut self.body, &mut bw)?;
         bytes_written += bw.write(&[NULL])? as u64;
 
         bw.flush().and(Ok(bytes_written))
     }
 
+    /// Like [`write_to`](Frame::write_to), but appends straight into a
+    /// caller-owned, reusable `buf` instead of going through `Write` and
+    /// allocating a `String` per header field.
+    pub fn encode_into(&mut self, buf: &mut Vec<u8>) -> io::Result<()> {
+        buf.extend_from_slice(self.command.as_str().as_bytes());
+        buf.push(EOL);
+        self.header.encode_into(buf);
+        buf.push(EOL);
+        io::copy(&mut self.body, buf)?;
+        buf.push(NULL);
+        Ok(())
+    }
+
+    /// Like [`write_to`](Frame::write_to), but batches the command and
+    /// headers into a single `writev`-style call instead of issuing a small
+    /// `write` per field, falling back to sequential writes when `w` doesn't
+    /// support vectoring. The body is still streamed straight to `w` (as
+    /// `write_to` does) rather than buffered, so a large `SEND` payload
+    /// doesn't cost an extra heap copy.
+    #[cfg(feature = "std")]
+    pub fn write_vectored_to<W: Write>(&mut self, mut w: W) -> io::Result<u64> {
+        let command_bytes = self.command.as_str().as_bytes();
+
+        let mut header_buf = Vec::new();
+        self.header.write_to(&mut header_buf)?;
+
+        let head: [&[u8]; 4] = [command_bytes, &[EOL], &header_buf, &[EOL]];
+        let mut bytes_written: u64 = head.iter().map(|p| p.len() as u64).sum();
+
+        if w.is_write_vectored() {
+            write_all_vectored(&mut w, &head)?;
+        } else {
+            for part in &head {
+                w.write_all(part)?;
+            }
+        }
+
+        bytes_written += io::copy(&mut self.body, &mut w)?;
+
+        w.write_all(&[NULL])?;
+        bytes_written += 1;
+        w.flush()?;
+
+        Ok(bytes_written)
+    }
+
     fn read_command<R: BufRead>(r: R) -> Result<Command, ReadError> {
         let mut command_reader = r.take(MAX_COMMAND_SIZE);
         let mut command_line_reader = DelimitedReader::new(&mut command_reader, EOL);
@@ -290,13 +467,15 @@ impl<'a> Frame<'a> {
         let command = Frame::read_command(&mut reader)?;
         let header = Header::read_from(&mut reader)?;
 
-        let clen = header
-            .get_field("Content-Length")
-            .map(|v| v.first())
-            .unwrap_or(None);
+        let clen = header.get_first("Content-Length");
 
         let body = match clen {
-            Some(n) => Body::with_length(reader, n.parse::<u64>().unwrap()),
+            Some(n) => {
+                let content_length = n
+                    .parse::<u64>()
+                    .map_err(|e| ReadError::Format(format!("invalid Content-Length: {}", e)))?;
+                Body::with_length(reader, content_length)
+            }
             None => Body::new(reader),
         };
         let frame = Frame::with_header(command, header, body);
@@ -310,6 +489,34 @@ impl<'a> Drop for Frame<'a> {
     }
 }
 
+/// A small pool of reusable [`Frame::encode_into`] buffers, so a sender can
+/// encode thousands of frames while reusing one growable backing buffer
+/// instead of allocating a fresh one per frame.
+#[derive(Default)]
+pub struct BufferPool {
+    buffers: Vec<Vec<u8>>,
+}
+
+impl BufferPool {
+    pub fn new() -> Self {
+        BufferPool {
+            buffers: Vec::new(),
+        }
+    }
+
+    /// Takes a buffer out of the pool, allocating a new empty one if the
+    /// pool is currently empty.
+    pub fn acquire(&mut self) -> Vec<u8> {
+        self.buffers.pop().unwrap_or_default()
+    }
+
+    /// Clears `buf` and returns it to the pool for reuse.
+    pub fn release(&mut self, mut buf: Vec<u8>) {
+        buf.clear();
+        self.buffers.push(buf);
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -359,7 +566,7 @@ mod test {
     #[test]
     fn write_frame() {
         let target = "CONNECT\nContent-Length: 30\nContent-Type: application/json\n\n\0";
-        let mut input = stdio::empty();
+        let mut input = io::empty();
         let mut frame = Frame::new(Command::Connect, Body::with_length(&mut input, 30));
         frame.header.add_field("Content-Type", "application/json");
         frame.header.add_field("Content-Length", "30");
@@ -422,4 +629,133 @@ mod test {
         assert_eq!(target_header, frame.header);
         assert_eq!(target_body, buffer);
     }
+
+    #[test]
+    fn repeated_header_round_trips_as_one_line_per_value() {
+        let mut header = Header::new();
+        header.add_field("Ack", "client");
+        header.add_field("Ack", "auto");
+
+        let mut buffer: Vec<u8> = Vec::new();
+        header.write_to(&mut buffer).unwrap();
+        let data = str::from_utf8(&buffer).unwrap();
+        assert_eq!("Ack: client\nAck: auto\n", data);
+
+        let mut reader = Cursor::new(buffer.as_slice());
+        let round_tripped = Header::read_from(&mut reader).unwrap();
+        assert_eq!(header, round_tripped);
+        assert_eq!(Some("client"), round_tripped.get_first("Ack"));
+    }
+
+    #[test]
+    fn encode_into_matches_write_to_for_repeated_header() {
+        let mut header = Header::new();
+        header.add_field("Ack", "client");
+        header.add_field("Ack", "auto");
+
+        let mut via_write_to: Vec<u8> = Vec::new();
+        header.write_to(&mut via_write_to).unwrap();
+
+        let mut via_encode_into: Vec<u8> = Vec::new();
+        header.encode_into(&mut via_encode_into);
+
+        assert_eq!(via_write_to, via_encode_into);
+    }
+
+    #[test]
+    fn header_value_escapes_and_unescapes_all_four_sequences() {
+        let mut header = Header::new();
+        header.add_field("X-Test", "a:b\\c\rd\ne");
+
+        let mut buffer: Vec<u8> = Vec::new();
+        header.write_to(&mut buffer).unwrap();
+        let data = str::from_utf8(&buffer).unwrap();
+        assert_eq!("X-Test: a\\cb\\\\c\\rd\\ne\n", data);
+
+        let mut reader = Cursor::new(buffer.as_slice());
+        let round_tripped = Header::read_from(&mut reader).unwrap();
+        assert_eq!(header, round_tripped);
+    }
+
+    #[test]
+    fn header_value_rejects_unknown_escape() {
+        let input = b"X-Test: a\\xb\n";
+        let mut reader = Cursor::new(&input[..]);
+        let err = Header::read_from(&mut reader).unwrap_err();
+        assert!(matches!(err, ReadError::Format(_)));
+    }
+
+    #[test]
+    fn header_value_rejects_trailing_backslash() {
+        let input = b"X-Test: ab\\\n";
+        let mut reader = Cursor::new(&input[..]);
+        let err = Header::read_from(&mut reader).unwrap_err();
+        assert!(matches!(err, ReadError::Format(_)));
+    }
+
+    #[test]
+    fn read_frame_with_invalid_content_length_is_a_format_error() {
+        let input = b"CONNECT\nContent-Length: abc\nContent-Type: application/json\n\n{}\0";
+        let mut reader = Cursor::new(&input[..]);
+        let err = Frame::read_from(&mut reader).unwrap_err();
+        assert!(matches!(err, ReadError::Format(_)));
+    }
+
+    #[test]
+    fn write_vectored_to_matches_write_to() {
+        let mut input_a = Cursor::new(b"{\"name\":\"Joshua\"}");
+        let mut frame_a = Frame::new(Command::Connect, Body::with_length(&mut input_a, 18));
+        frame_a.header.add_field("Content-Type", "application/json");
+        frame_a.header.add_field("Content-Length", "18");
+
+        let mut via_write_to: Vec<u8> = Vec::new();
+        frame_a.write_to(&mut via_write_to).unwrap();
+
+        let mut input_b = Cursor::new(b"{\"name\":\"Joshua\"}");
+        let mut frame_b = Frame::new(Command::Connect, Body::with_length(&mut input_b, 18));
+        frame_b.header.add_field("Content-Type", "application/json");
+        frame_b.header.add_field("Content-Length", "18");
+
+        let mut via_write_vectored_to: Vec<u8> = Vec::new();
+        let bytes_written = frame_b
+            .write_vectored_to(&mut via_write_vectored_to)
+            .unwrap();
+
+        assert_eq!(via_write_to, via_write_vectored_to);
+        assert_eq!(via_write_to.len() as u64, bytes_written);
+    }
+
+    #[test]
+    fn buffer_pool_reuses_released_buffers() {
+        let mut pool = BufferPool::new();
+
+        let mut buf = pool.acquire();
+        assert!(buf.is_empty());
+        buf.extend_from_slice(b"CONNECT\n");
+        let ptr_before = buf.as_ptr();
+
+        pool.release(buf);
+
+        let reused = pool.acquire();
+        assert!(reused.is_empty());
+        assert_eq!(ptr_before, reused.as_ptr());
+    }
+
+    #[test]
+    fn body_read_with_a_small_buffer_does_not_drop_bytes_before_the_null_terminator() {
+        let mut input = Cursor::new(b"hello world\0after".to_vec());
+        let mut body = Body::new(&mut input);
+
+        let mut out: Vec<u8> = Vec::new();
+        let mut chunk = [0u8; 3];
+        loop {
+            let n = body.read(&mut chunk).unwrap();
+            if n == 0 {
+                break;
+            }
+            out.extend_from_slice(&chunk[..n]);
+        }
+
+        assert_eq!(b"hello world".to_vec(), out);
+    }
 }
@@ -0,0 +1,193 @@
+//! Optional `serde` support.
+//!
+//! The binary STOMP encoding stays authoritative; this module only adds a
+//! JSON-friendly mirror of it (`Command` as its wire string, `Header` as a
+//! JSON object of `name -> [values]`, and [`FrameView`] as a fully-buffered
+//! snapshot of a [`Frame`]) for structured logging and HTTP/JSON gateways.
+//!
+//! This is a `std`-only mirror: JSON bridging and logging are inherently
+//! `std` use cases, so unlike the rest of the crate this module is gated on
+//! `feature = "std"` (see the `mod serde_support` declaration) rather than
+//! routed through the `no_std` facade.
+
+use super::{Body, Command, Frame, Header};
+use core::fmt;
+use core::str::FromStr;
+use serde::de::{self, Deserializer, Visitor};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io;
+use std::io::Cursor;
+use std::string::String;
+use std::vec::Vec;
+
+impl Serialize for Command {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Command {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct CommandVisitor;
+
+        impl<'de> Visitor<'de> for CommandVisitor {
+            type Value = Command;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str("a STOMP command string, e.g. \"SEND\"")
+            }
+
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<Command, E> {
+                Command::from_str(v).map_err(de::Error::custom)
+            }
+        }
+
+        deserializer.deserialize_str(CommandVisitor)
+    }
+}
+
+impl Serialize for Header {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.fields.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Header {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let fields = BTreeMap::<String, Vec<String>>::deserialize(deserializer)?;
+        Ok(Header { fields })
+    }
+}
+
+/// An owned, fully-buffered, serde-friendly snapshot of a [`Frame`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FrameView {
+    pub command: Command,
+    pub headers: Header,
+    /// JSON has no binary type, so the derive's default `Vec<u8>` impl (and
+    /// even `#[serde(with = "serde_bytes")]`, which `serde_json` still has to
+    /// fall back to a per-byte array for) would turn a body into a
+    /// comma-separated array of integers. Base64-encode it as a string
+    /// instead, which is both compact and what JSON/HTTP consumers expect.
+    #[serde(with = "body_base64")]
+    pub body: Vec<u8>,
+}
+
+mod body_base64 {
+    use super::{String, Vec};
+    use base64::Engine as _;
+    use serde::{de, Deserialize, Deserializer, Serializer};
+
+    pub(super) fn serialize<S: Serializer>(body: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&base64::engine::general_purpose::STANDARD.encode(body))
+    }
+
+    pub(super) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<u8>, D::Error> {
+        let encoded = String::deserialize(deserializer)?;
+        base64::engine::general_purpose::STANDARD
+            .decode(encoded.as_bytes())
+            .map_err(de::Error::custom)
+    }
+}
+
+impl FrameView {
+    /// Drains `frame`'s body into memory and snapshots its command and
+    /// headers. `frame` is left with an empty header afterwards since its
+    /// contents have been moved into the returned view.
+    pub fn from_frame(frame: &mut Frame<'_>) -> io::Result<Self> {
+        let mut body = Vec::new();
+        io::copy(&mut frame.body, &mut body)?;
+
+        Ok(FrameView {
+            command: frame.command,
+            headers: core::mem::replace(&mut frame.header, Header::new()),
+            body,
+        })
+    }
+
+    /// Builds a [`Frame`] that reads its body back out of `scratch`. `scratch`
+    /// only needs to outlive the returned `Frame`, not `self` — so the same
+    /// `scratch` can be reused across calls to `to_frame` on different
+    /// `FrameView`s, one at a time, as long as each `Frame` is dropped before
+    /// the next call reuses it.
+    pub fn to_frame<'a, 'b>(&'a self, scratch: &'b mut Cursor<&'a [u8]>) -> Frame<'b>
+    where
+        'a: 'b,
+    {
+        *scratch = Cursor::new(self.body.as_slice());
+
+        let body = if self.body.is_empty() {
+            Body::new(scratch)
+        } else {
+            Body::with_length(scratch, self.body.len() as u64)
+        };
+        Frame::with_header(self.command, self.headers.clone(), body)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn frame_view_round_trips_through_json() {
+        let mut input = Cursor::new(b"{\"name\":\"Joshua\"}".to_vec());
+        let mut frame = Frame::new(Command::Send, Body::with_length(&mut input, 18));
+        frame.header.add_field("Content-Type", "application/json");
+
+        let view = FrameView::from_frame(&mut frame).unwrap();
+        let json = serde_json::to_string(&view).unwrap();
+        let round_tripped: FrameView = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(view, round_tripped);
+        assert_eq!(Command::Send, round_tripped.command);
+        assert_eq!(b"{\"name\":\"Joshua\"}".to_vec(), round_tripped.body);
+    }
+
+    #[test]
+    fn frame_view_body_is_a_base64_json_string_not_a_byte_array() {
+        let view = FrameView {
+            command: Command::Send,
+            headers: Header::new(),
+            body: b"hi".to_vec(),
+        };
+
+        let json: serde_json::Value = serde_json::to_value(&view).unwrap();
+        assert_eq!(
+            serde_json::Value::String(String::from("aGk=")),
+            json["body"]
+        );
+    }
+
+    #[test]
+    fn to_frame_reuses_scratch_across_calls() {
+        let mut first = FrameView {
+            command: Command::Send,
+            headers: Header::new(),
+            body: b"first".to_vec(),
+        };
+        first.headers.add_field("Content-Type", "text/plain");
+
+        let second = FrameView {
+            command: Command::Send,
+            headers: Header::new(),
+            body: b"second".to_vec(),
+        };
+
+        let mut scratch = Cursor::new(&b""[..]);
+
+        let mut buffer: Vec<u8> = Vec::new();
+        first.to_frame(&mut scratch).write_to(&mut buffer).unwrap();
+        assert_eq!(
+            b"SEND\nContent-Type: text/plain\n\nfirst\0".to_vec(),
+            buffer
+        );
+
+        buffer.clear();
+        second.to_frame(&mut scratch).write_to(&mut buffer).unwrap();
+        assert_eq!(b"SEND\n\nsecond\0".to_vec(), buffer);
+    }
+}
@@ -0,0 +1,69 @@
+//! STOMP 1.2 header value escaping (section "Value Encoding" of the spec):
+//! `\r`, `\n`, `:`, and `\` are escaped on the wire as `\r`, `\n`, `\c`, and
+//! `\\` respectively; no other backslash escape is legal.
+
+use super::error::ReadError;
+
+#[cfg(feature = "std")]
+use std::string::String;
+#[cfg(feature = "std")]
+use std::vec::Vec;
+
+#[cfg(not(feature = "std"))]
+use alloc::format;
+#[cfg(not(feature = "std"))]
+use alloc::string::String;
+#[cfg(not(feature = "std"))]
+use alloc::vec::Vec;
+
+pub(crate) fn encode(value: &str) -> String {
+    let mut buf = Vec::with_capacity(value.len());
+    encode_into(value, &mut buf);
+    // `encode_into` only ever appends ASCII escape sequences or bytes copied
+    // verbatim from the input `&str`, so the result is still valid UTF-8.
+    String::from_utf8(buf).expect("encoded header value is valid utf-8")
+}
+
+pub(crate) fn decode(value: &str) -> Result<String, ReadError> {
+    let mut decoded = String::with_capacity(value.len());
+    let mut chars = value.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            decoded.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('r') => decoded.push('\r'),
+            Some('n') => decoded.push('\n'),
+            Some('c') => decoded.push(':'),
+            Some('\\') => decoded.push('\\'),
+            Some(other) => {
+                return Err(ReadError::Format(format!(
+                    "invalid header escape sequence '\\{}'",
+                    other
+                )))
+            }
+            None => {
+                return Err(ReadError::Format(String::from(
+                    "header value ends with a trailing backslash",
+                )))
+            }
+        }
+    }
+    Ok(decoded)
+}
+
+/// Same escaping as [`encode`], but appends straight into `buf` instead of
+/// allocating a new `String` per call.
+pub(crate) fn encode_into(value: &str, buf: &mut Vec<u8>) {
+    for byte in value.bytes() {
+        match byte {
+            b'\r' => buf.extend_from_slice(b"\\r"),
+            b'\n' => buf.extend_from_slice(b"\\n"),
+            b':' => buf.extend_from_slice(b"\\c"),
+            b'\\' => buf.extend_from_slice(b"\\\\"),
+            other => buf.push(other),
+        }
+    }
+}